@@ -0,0 +1,101 @@
+//! TOML config file format for `--config`/`--daemon` mode.
+
+use crate::fan;
+use eyre::{eyre, Context, Result};
+use serde::Deserialize;
+use std::{fs, path::Path};
+
+/// top-level `nvml-tune.toml` document: one `[[gpu]]` table per managed
+/// device, plus settings shared across all of them.
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    /// how long to sleep in between fan speed changes, shared by every
+    /// device's fan-control loop.
+    #[serde(default = "default_fan_update_duration")]
+    pub fan_update_duration: u64,
+
+    /// dead-band (in celsius) around the point that last triggered a
+    /// fan duty change, as in `--fan-hysteresis`.
+    #[serde(default = "default_fan_hysteresis")]
+    pub fan_hysteresis: u32,
+
+    /// suppress fan duty changes smaller than this many percent, as in
+    /// `--fan-min-delta`.
+    #[serde(default)]
+    pub fan_min_delta: u32,
+
+    #[serde(rename = "gpu", default)]
+    pub gpus: Vec<GpuConfig>,
+}
+
+/// a single `[[gpu]]` table, selecting one device and describing the
+/// settings to apply to it.
+#[derive(Debug, Deserialize)]
+pub struct GpuConfig {
+    /// selects the device by index, as in `--device <INDEX>`.
+    pub index: Option<u32>,
+    /// selects the device by UUID (`GPU-xxxx`), stable across reboots.
+    pub uuid: Option<String>,
+    /// selects the device by PCI bus ID (e.g. `0000:01:00.0`).
+    pub pci_bus_id: Option<String>,
+
+    pub tdp: Option<u32>,
+    pub mclk_offset: Option<i32>,
+    pub gclk_offset: Option<i32>,
+
+    /// locks the graphics clock to a `(min, max)` range (MHz), as in
+    /// `--gclk-lock`.
+    pub gclk_lock: Option<(u32, u32)>,
+    /// locks the memory clock to a `(min, max)` range (MHz), as in
+    /// `--mclk-lock`.
+    pub mclk_lock: Option<(u32, u32)>,
+    /// resets any locked graphics/memory clocks back to the gpu's
+    /// defaults, as in `--reset-clocks`.
+    #[serde(default)]
+    pub reset_clocks: bool,
+
+    /// fan speed curve in the same `(temp, duty)` keypoint format as
+    /// `Args::fan_curve`, empty meaning "don't manage this gpu's fan".
+    /// normalized by [`Config::load`] via [`fan::normalize_curve`], the
+    /// same pass the CLI's `--fan-curve` goes through, so a raw
+    /// `[[40, 30], [85, 100]]` gets the same `(0, 0)`/`(100, 100)`
+    /// anchors as the equivalent CLI curve.
+    #[serde(default)]
+    pub fan_curve: Vec<(u32, u32)>,
+}
+
+fn default_fan_update_duration() -> u64 {
+    2
+}
+
+fn default_fan_hysteresis() -> u32 {
+    2
+}
+
+impl Config {
+    /// loads and parses `path`, then normalizes every `[[gpu]]` entry's
+    /// fan curve through [`fan::normalize_curve`] so config-file curves
+    /// are sorted/anchored exactly like `--fan-curve` ones before they
+    /// ever reach the fan evaluator. a gpu with no fan curve configured
+    /// is left alone.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file {}", path.display()))?;
+        let mut config: Config = toml::from_str(&text)
+            .with_context(|| format!("failed to parse config file {}", path.display()))?;
+
+        for gpu in &mut config.gpus {
+            if gpu.fan_curve.is_empty() {
+                continue;
+            }
+            gpu.fan_curve = fan::normalize_curve(&gpu.fan_curve).map_err(|err| {
+                eyre!(
+                    "invalid fan curve for gpu in config file {}: {err}",
+                    path.display()
+                )
+            })?;
+        }
+
+        Ok(config)
+    }
+}
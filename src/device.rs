@@ -0,0 +1,143 @@
+//! `--device` selector: index, UUID, PCI bus ID, or `all`.
+
+use crate::error::check;
+use eyre::Result;
+use nvml_wrapper_sys::bindings::{nvmlDevice_t, NvmlLib};
+use std::{ffi::CString, mem::MaybeUninit, str::FromStr};
+
+/// how `--device` picked the gpu(s) to manage.
+#[derive(Debug, Clone)]
+pub enum Selector {
+    Index(u32),
+    Uuid(String),
+    PciBusId(String),
+    All,
+}
+
+impl FromStr for Selector {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(if s.eq_ignore_ascii_case("all") {
+            Selector::All
+        } else if s.starts_with("GPU-")
+            || s.starts_with("gpu-")
+            || s.starts_with("MIG-")
+            || s.starts_with("mig-")
+        {
+            // nvml resolves both full-gpu and mig-instance uuids through
+            // the same handle lookup, so no separate variant is needed.
+            Selector::Uuid(s.to_owned())
+        } else if s.contains(':') {
+            Selector::PciBusId(s.to_owned())
+        } else if let Ok(index) = s.parse::<u32>() {
+            Selector::Index(index)
+        } else {
+            // not a recognized format; let nvml reject it as a uuid so
+            // the user gets a consistent nvml-flavored error message.
+            Selector::Uuid(s.to_owned())
+        })
+    }
+}
+
+/// resolves `selector` to the handle(s) of every matching device.
+pub fn resolve(lib: &NvmlLib, selector: &Selector) -> Result<Vec<nvmlDevice_t>> {
+    match selector {
+        Selector::All => {
+            let mut count = 0;
+            check(lib, unsafe { lib.nvmlDeviceGetCount_v2(&mut count) })?;
+            (0..count).map(|index| by_index(lib, index)).collect()
+        }
+        Selector::Index(index) => Ok(vec![by_index(lib, *index)?]),
+        Selector::Uuid(uuid) => Ok(vec![by_uuid(lib, uuid)?]),
+        Selector::PciBusId(pci_bus_id) => Ok(vec![by_pci_bus_id(lib, pci_bus_id)?]),
+    }
+}
+
+/// reads `device`'s own index, for tagging telemetry/log lines.
+pub fn index_of(lib: &NvmlLib, device: nvmlDevice_t) -> Result<u32> {
+    let mut index = 0;
+    check(lib, unsafe { lib.nvmlDeviceGetIndex(device, &mut index) })?;
+    Ok(index)
+}
+
+/// true if `device` is a mig instance handle rather than a full gpu, in
+/// which case physically-global operations (tdp, clock offsets/locks,
+/// fan) must be gated to its parent device.
+pub fn is_mig_device_handle(lib: &NvmlLib, device: nvmlDevice_t) -> Result<bool> {
+    let mut is_mig = 0;
+    check(lib, unsafe {
+        lib.nvmlDeviceIsMigDeviceHandle(device, &mut is_mig)
+    })?;
+    Ok(is_mig != 0)
+}
+
+/// enumerates `parent`'s mig instances, or an empty list if mig mode is
+/// disabled (or unsupported) on it.
+pub fn mig_instances(lib: &NvmlLib, parent: nvmlDevice_t) -> Result<Vec<nvmlDevice_t>> {
+    let mut mode = 0;
+    let mut pending_mode = 0;
+    match check(lib, unsafe {
+        lib.nvmlDeviceGetMigMode(parent, &mut mode, &mut pending_mode)
+    }) {
+        Ok(()) => {}
+        Err(err) if err.is_not_supported() => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    }
+
+    if mode == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut max_count = 0;
+    check(lib, unsafe {
+        lib.nvmlDeviceGetMaxMigDeviceCount(parent, &mut max_count)
+    })?;
+
+    let mut instances = Vec::with_capacity(max_count as usize);
+    for i in 0..max_count {
+        let mut instance = MaybeUninit::uninit();
+        match check(lib, unsafe {
+            lib.nvmlDeviceGetMigDeviceHandleByIndex(parent, i, instance.as_mut_ptr())
+        }) {
+            Ok(()) => instances.push(unsafe { instance.assume_init() }),
+            // an index below `max_count` can still be an empty, unpopulated
+            // instance slot: nvml reports that as `NotFound`, not
+            // `NotSupported`. skip it either way instead of aborting the
+            // whole enumeration.
+            Err(err) if err.is_not_supported() || err.is_not_found() => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    Ok(instances)
+}
+
+pub(crate) fn by_index(lib: &NvmlLib, index: u32) -> Result<nvmlDevice_t> {
+    let mut device = MaybeUninit::uninit();
+    check(lib, unsafe {
+        lib.nvmlDeviceGetHandleByIndex_v2(index, device.as_mut_ptr())
+    })
+    .map_err(|err| eyre::eyre!("failed to get device at index {index}! ({err})"))?;
+    Ok(unsafe { device.assume_init() })
+}
+
+pub(crate) fn by_uuid(lib: &NvmlLib, uuid: &str) -> Result<nvmlDevice_t> {
+    let uuid_cstr = CString::new(uuid)?;
+    let mut device = MaybeUninit::uninit();
+    check(lib, unsafe {
+        lib.nvmlDeviceGetHandleByUUID(uuid_cstr.as_ptr(), device.as_mut_ptr())
+    })
+    .map_err(|err| eyre::eyre!("failed to get device with uuid {uuid}! ({err})"))?;
+    Ok(unsafe { device.assume_init() })
+}
+
+pub(crate) fn by_pci_bus_id(lib: &NvmlLib, pci_bus_id: &str) -> Result<nvmlDevice_t> {
+    let pci_bus_id_cstr = CString::new(pci_bus_id)?;
+    let mut device = MaybeUninit::uninit();
+    check(lib, unsafe {
+        lib.nvmlDeviceGetHandleByPciBusId_v2(pci_bus_id_cstr.as_ptr(), device.as_mut_ptr())
+    })
+    .map_err(|err| eyre::eyre!("failed to get device with pci bus id {pci_bus_id}! ({err})"))?;
+    Ok(unsafe { device.assume_init() })
+}
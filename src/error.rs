@@ -0,0 +1,87 @@
+//! typed wrapper around raw `nvmlReturn_t` codes.
+
+use nvml_wrapper_sys::bindings::NvmlLib;
+use std::ffi::CStr;
+use thiserror::Error;
+
+/// an nvml call that returned a non-success `nvmlReturn_t`.
+///
+/// the common, actionable codes get their own variant so callers can
+/// react differently (e.g. treat `NotSupported` as a soft warning); the
+/// rest fall back to `Other`, carrying whatever message
+/// `nvmlErrorString` resolves the code to.
+#[derive(Debug, Error)]
+pub enum NvmlError {
+    #[error("not supported: {0}")]
+    NotSupported(String),
+    #[error("no permission: {0}")]
+    NoPermission(String),
+    #[error("invalid argument: {0}")]
+    InvalidArgument(String),
+    #[error("gpu is lost: {0}")]
+    GpuIsLost(String),
+    #[error("not found: {0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl NvmlError {
+    /// true if this error means the call was rejected as unsupported by
+    /// the device/driver rather than failing outright.
+    pub fn is_not_supported(&self) -> bool {
+        matches!(self, NvmlError::NotSupported(_))
+    }
+
+    /// true if this error means the queried object (e.g. a mig instance
+    /// slot below `MaxMigDeviceCount`) simply doesn't exist.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, NvmlError::NotFound(_))
+    }
+
+    /// a mig instance handle was given a physically-global operation
+    /// (tdp, clock offsets/locks, fan) that only its parent device can
+    /// accept.
+    pub fn mig_child_unsupported(operation: &str) -> Self {
+        NvmlError::NotSupported(format!(
+            "{operation} is not supported on a mig instance, target the parent gpu instead"
+        ))
+    }
+}
+
+// nvmlReturn_t codes relevant to the variants above; see nvml.h.
+const NVML_SUCCESS: u32 = 0;
+const NVML_ERROR_NOT_SUPPORTED: u32 = 3;
+const NVML_ERROR_NO_PERMISSION: u32 = 4;
+const NVML_ERROR_INVALID_ARGUMENT: u32 = 2;
+const NVML_ERROR_GPU_IS_LOST: u32 = 15;
+const NVML_ERROR_NOT_FOUND: u32 = 6;
+
+fn resolve(lib: &NvmlLib, code: u32) -> String {
+    let msg = unsafe { lib.nvmlErrorString(code) };
+    if msg.is_null() {
+        return format!("unknown error (code = {code})");
+    }
+    unsafe { CStr::from_ptr(msg) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// turns a raw `nvmlReturn_t` into a `Result`, resolving non-success
+/// codes into a typed, human-readable [`NvmlError`] via
+/// `nvmlErrorString`.
+pub fn check(lib: &NvmlLib, ret: u32) -> Result<(), NvmlError> {
+    if ret == NVML_SUCCESS {
+        return Ok(());
+    }
+
+    let msg = resolve(lib, ret);
+    Err(match ret {
+        NVML_ERROR_NOT_SUPPORTED => NvmlError::NotSupported(msg),
+        NVML_ERROR_NO_PERMISSION => NvmlError::NoPermission(msg),
+        NVML_ERROR_INVALID_ARGUMENT => NvmlError::InvalidArgument(msg),
+        NVML_ERROR_GPU_IS_LOST => NvmlError::GpuIsLost(msg),
+        NVML_ERROR_NOT_FOUND => NvmlError::NotFound(msg),
+        _ => NvmlError::Other(msg),
+    })
+}
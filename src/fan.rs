@@ -0,0 +1,201 @@
+//! fan speed curve evaluation, shared between one-shot and daemon mode.
+
+/// compute the fan duty (%) for `temp` (°C) given a 0-anchored,
+/// 100-anchored `(temp, duty)` keypoint curve, by linearly interpolating
+/// between the two keypoints surrounding `temp`.
+///
+/// the curve is expected to already be sorted by temperature (as
+/// [`normalize_curve`] guarantees), but is sorted defensively here too:
+/// an out-of-order curve must never reach the `windows(2)` search below
+/// with no matching pair, which would otherwise panic.
+pub fn duty_for_temp(curve: &[(u32, u32)], temp: u32) -> u32 {
+    let mut curve = curve.to_vec();
+    curve.sort_by_key(|(temp, _)| *temp);
+
+    let (first_temp, first_duty) = curve[0];
+    if temp <= first_temp {
+        return first_duty;
+    }
+
+    let (last_temp, last_duty) = curve[curve.len() - 1];
+    if temp >= last_temp {
+        return last_duty;
+    }
+
+    let Some((t0, d0, t1, d1)) = curve.windows(2).find_map(|w| {
+        (w[0].0 <= temp && temp <= w[1].0).then_some((w[0].0, w[0].1, w[1].0, w[1].1))
+    }) else {
+        // with the curve sorted and `temp` strictly between its first and
+        // last keypoints some pair always brackets `temp`, duplicate
+        // temperatures included; this only guards that invariant, falling
+        // back to the nearest keypoint's duty rather than panicking.
+        return curve
+            .iter()
+            .min_by_key(|(t, _)| t.abs_diff(temp))
+            .map_or(first_duty, |(_, d)| *d);
+    };
+
+    let duty =
+        d0 as i64 + (d1 as i64 - d0 as i64) * (temp as i64 - t0 as i64) / (t1 as i64 - t0 as i64);
+    duty as u32
+}
+
+/// validates and normalizes a raw `(temp, duty)` keypoint curve: dedupes
+/// points sharing a temperature (keeping the higher duty), sorts by
+/// temperature, and inserts the `(0, 0)`/`(100, 100)` anchors if
+/// missing. rejects an empty curve or one with an out-of-range
+/// (>100) temperature or duty.
+pub fn normalize_curve(points: &[(u32, u32)]) -> Result<Vec<(u32, u32)>, String> {
+    let mut curve: Vec<(u32, u32)> = Vec::with_capacity(points.len());
+    for &(temp, duty) in points {
+        if temp > 100 || duty > 100 {
+            return Err(format!(
+                "fan curve point ({temp}:{duty}) must be within 0-100"
+            ));
+        }
+
+        match curve.iter_mut().find(|(t, _)| *t == temp) {
+            Some((_, d)) if duty > *d => *d = duty,
+            Some(_) => {}
+            None => curve.push((temp, duty)),
+        }
+    }
+
+    if curve.is_empty() {
+        return Err("fan curve must not be empty!".to_owned());
+    }
+    curve.sort_by_key(|(temp, _)| *temp);
+
+    if curve[0].0 != 0 {
+        curve.insert(0, (0, 0));
+    }
+    if curve.last().is_some_and(|(temp, _)| *temp < 100) {
+        curve.push((100, 100));
+    }
+
+    Ok(curve)
+}
+
+/// tracks the last fan duty change to apply temperature hysteresis on
+/// top of [`duty_for_temp`], so small fluctuations around a curve
+/// keypoint don't make the fan oscillate.
+#[derive(Debug, Default)]
+pub struct Hysteresis {
+    last_trigger_temp: Option<u32>,
+    last_duty: Option<u32>,
+}
+
+impl Hysteresis {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// decides the fan duty for `temp`, or `None` if it's still within
+    /// the dead-band around the last point that triggered a change and
+    /// should be left alone.
+    ///
+    /// `hysteresis` is the dead-band width in °C, applied in full when
+    /// the temperature is rising since the last trigger and halved when
+    /// it's falling, so the fan ramps up cautiously but backs off
+    /// promptly. `min_delta` suppresses duty changes smaller than it.
+    pub fn tick(
+        &mut self,
+        curve: &[(u32, u32)],
+        temp: u32,
+        hysteresis: u32,
+        min_delta: u32,
+    ) -> Option<u32> {
+        let duty = duty_for_temp(curve, temp);
+
+        let Some(last_trigger_temp) = self.last_trigger_temp else {
+            self.last_trigger_temp = Some(temp);
+            self.last_duty = Some(duty);
+            return Some(duty);
+        };
+
+        let rising = temp > last_trigger_temp;
+        let threshold = if rising { hysteresis } else { hysteresis / 2 };
+        if temp.abs_diff(last_trigger_temp) < threshold {
+            return None;
+        }
+
+        let last_duty = self.last_duty.unwrap_or(duty);
+        if duty.abs_diff(last_duty) < min_delta {
+            return None;
+        }
+
+        self.last_trigger_temp = Some(temp);
+        self.last_duty = Some(duty);
+        Some(duty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duty_for_temp_clamps_below_and_above_the_curve() {
+        let curve = [(20, 30), (50, 60)];
+        assert_eq!(duty_for_temp(&curve, 0), 30); // below the first keypoint
+        assert_eq!(duty_for_temp(&curve, 20), 30); // at the first keypoint
+        assert_eq!(duty_for_temp(&curve, 100), 60); // above the last keypoint
+    }
+
+    #[test]
+    fn duty_for_temp_interpolates_linearly_between_keypoints() {
+        let curve = [(0, 0), (100, 100)];
+        assert_eq!(duty_for_temp(&curve, 25), 25);
+        assert_eq!(duty_for_temp(&curve, 50), 50);
+
+        let curve = [(40, 30), (85, 100)];
+        assert_eq!(duty_for_temp(&curve, 40), 30);
+        assert_eq!(duty_for_temp(&curve, 85), 100);
+        assert_eq!(duty_for_temp(&curve, 62), 64); // interpolated, rounded down
+    }
+
+    #[test]
+    fn duty_for_temp_tolerates_an_unsorted_curve() {
+        let curve = [(100, 100), (0, 0), (50, 50)];
+        assert_eq!(duty_for_temp(&curve, 25), 25);
+    }
+
+    #[test]
+    fn normalize_curve_sorts_dedupes_and_anchors() {
+        let curve = normalize_curve(&[(85, 100), (40, 30), (40, 20)]).unwrap();
+        assert_eq!(curve, vec![(0, 0), (40, 30), (85, 100), (100, 100)]);
+    }
+
+    #[test]
+    fn normalize_curve_rejects_empty_and_out_of_range_points() {
+        assert!(normalize_curve(&[]).is_err());
+        assert!(normalize_curve(&[(101, 50)]).is_err());
+        assert!(normalize_curve(&[(50, 101)]).is_err());
+    }
+
+    #[test]
+    fn hysteresis_suppresses_changes_within_the_dead_band() {
+        let curve = [(0, 0), (100, 100)];
+        let mut h = Hysteresis::new();
+        assert_eq!(h.tick(&curve, 50, 4, 0), Some(50));
+
+        // rising but inside the full dead-band: suppressed.
+        assert_eq!(h.tick(&curve, 52, 4, 0), None);
+        // rising past the full dead-band: triggers.
+        assert_eq!(h.tick(&curve, 55, 4, 0), Some(55));
+
+        // falling: only the halved dead-band applies.
+        assert_eq!(h.tick(&curve, 54, 4, 0), None);
+        assert_eq!(h.tick(&curve, 52, 4, 0), Some(52));
+    }
+
+    #[test]
+    fn hysteresis_suppresses_changes_below_min_delta() {
+        let curve = [(0, 0), (100, 100)];
+        let mut h = Hysteresis::new();
+        assert_eq!(h.tick(&curve, 50, 0, 10), Some(50));
+        // past the (zero) temperature dead-band, but duty barely moved.
+        assert_eq!(h.tick(&curve, 55, 0, 10), None);
+        assert_eq!(h.tick(&curve, 65, 0, 10), Some(65));
+    }
+}
@@ -1,8 +1,27 @@
+mod config;
+mod device;
+mod error;
+mod fan;
+mod metrics;
+
 use clap::Parser;
+use config::{Config, GpuConfig};
+use error::{check, NvmlError};
 use eyre::{bail, eyre, Result};
 use nom::IResult;
 use nvml_wrapper_sys::bindings::*;
-use std::{fs::File, io, mem::MaybeUninit, path::PathBuf, sync::mpsc, thread, time::Duration};
+use signal_hook::{consts::SIGHUP, iterator::Signals};
+use std::{
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
 use sudo::RunningAs;
 use tracing::{error, info, Level};
 use tracing_subscriber::{prelude::*, EnvFilter};
@@ -40,95 +59,493 @@ fn main() -> Result<()> {
     let lib = unsafe { NvmlLib::new("libnvidia-ml.so")? };
     info!("loaded nvml!");
 
-    match unsafe { lib.nvmlInit_v2() } {
-        0 => info!("initialized nvml!"),
-        val => bail!("failed to initialize nvml! (error {val})"),
-    }
+    check(&lib, unsafe { lib.nvmlInit_v2() })?;
+    info!("initialized nvml!");
 
-    let mut device = MaybeUninit::uninit();
-    match unsafe { lib.nvmlDeviceGetHandleByIndex_v2(args.index, device.as_mut_ptr()) } {
-        0 => info!("got device at index {}! (addr = {:p})", args.index, &device),
-        val => bail!(
-            "failed to get device at index {}! (error = {val})",
-            args.index
-        ),
+    if args.daemon {
+        let config_path = args
+            .config
+            .as_ref()
+            .expect("clap enforces --daemon requires --config");
+        return run_daemon(&lib, config_path);
     }
-    let device = unsafe { device.assume_init() };
 
-    if let Some(tdp) = args.tdp {
-        match unsafe { lib.nvmlDeviceSetPowerManagementLimit(device, tdp * 1000) } {
-            0 => info!("set tdp to {tdp}W!"),
-            val => error!("failed to set tdp! (error = {val})"),
+    let devices = device::resolve(&lib, &args.device)?;
+    info!(
+        "resolved {} device(s) for --device {:?}!",
+        devices.len(),
+        args.device
+    );
+
+    let static_settings = StaticSettings {
+        tdp: args.tdp,
+        mclk_offset: args.mclk_offset,
+        gclk_offset: args.gclk_offset,
+        gclk_lock: args.gclk_lock,
+        mclk_lock: args.mclk_lock,
+        reset_clocks: args.reset_clocks,
+    };
+    for &device in &devices {
+        match device::mig_instances(&lib, device) {
+            Ok(instances) if !instances.is_empty() => {
+                info!(
+                    "gpu: mig mode enabled with {} instance(s)!",
+                    instances.len()
+                )
+            }
+            Ok(_) => {}
+            Err(err) => error!("failed to enumerate mig instances! ({err})"),
         }
+
+        let is_mig_child = device::is_mig_device_handle(&lib, device)?;
+        apply_static_settings(&lib, device, is_mig_child, &static_settings);
     }
 
-    if let Some(mem_clock) = args.mclk_offset {
-        match unsafe { lib.nvmlDeviceSetMemClkVfOffset(device, mem_clock * 2) } {
-            0 => info!("set memory clock offset to +{mem_clock}MHz!"),
-            val => error!("failed to set memory clock offset! (error = {val})"),
+    if args.fan_curve.is_some() || args.metrics.is_some() {
+        let stop = Arc::new(AtomicBool::new(false));
+        {
+            let (tx, rx) = mpsc::channel();
+            ctrlc::set_handler(move || tx.send(()).unwrap())?;
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || {
+                let _ = rx.recv();
+                stop.store(true, Ordering::SeqCst);
+            });
         }
+
+        thread::scope(|scope| -> Result<()> {
+            for &device in &devices {
+                let index = device::index_of(&lib, device)?;
+                let is_mig_child = device::is_mig_device_handle(&lib, device)?;
+
+                if let Some(metrics_path) = &args.metrics {
+                    let stop = Arc::clone(&stop);
+                    scope.spawn(move || {
+                        if let Err(err) = metrics::run(
+                            &lib,
+                            device,
+                            index,
+                            metrics_path,
+                            args.metrics_interval,
+                            &stop,
+                        ) {
+                            error!("gpu #{index}: metrics thread exited! ({err})");
+                        }
+                    });
+                }
+
+                if let Some(fan_curve) = &args.fan_curve {
+                    let stop = Arc::clone(&stop);
+                    scope.spawn(move || {
+                        if is_mig_child {
+                            error!(
+                                "gpu #{index}: {}",
+                                NvmlError::mig_child_unsupported("fan control")
+                            );
+                            return;
+                        }
+
+                        if fan_curve.len() == 1 && fan_curve[0].0 > 0 {
+                            error!("single point fan curve must have a 0c point!");
+                            return;
+                        }
+
+                        let mut hysteresis = fan::Hysteresis::new();
+                        while !stop.load(Ordering::SeqCst) {
+                            let mut temp = 0;
+                            match check(&lib, unsafe {
+                                lib.nvmlDeviceGetTemperature(device, 0, &mut temp)
+                            }) {
+                                Ok(()) => {
+                                    info!("gpu #{index}: read current temperature! ({temp}c)")
+                                }
+                                Err(err) => {
+                                    error!(
+                                        "gpu #{index}: failed to read current temperature! ({err})"
+                                    )
+                                }
+                            }
+
+                            if let Some(duty) = hysteresis.tick(
+                                fan_curve,
+                                temp,
+                                args.fan_hysteresis,
+                                args.fan_min_delta,
+                            ) {
+                                match check(&lib, unsafe {
+                                    lib.nvmlDeviceSetFanSpeed_v2(device, 0, duty)
+                                }) {
+                                    Ok(()) => info!("gpu #{index}: set fan duty to {duty}%!"),
+                                    Err(err) => {
+                                        error!("gpu #{index}: failed to set fan duty! ({err})")
+                                    }
+                                }
+                            }
+
+                            thread::sleep(Duration::from_secs(args.fan_update_duration));
+                        }
+                    });
+                }
+            }
+
+            Ok(())
+        })?;
     }
 
-    if let Some(gfx_clock) = args.gclk_offset {
-        match unsafe { lib.nvmlDeviceSetGpcClkVfOffset(device, gfx_clock) } {
-            0 => info!("set graphics clock offset to +{gfx_clock}MHz!"),
-            val => error!("failed to set graphics clock! (error = {val})"),
-        }
+    unsafe {
+        lib.nvmlShutdown();
     }
 
-    if let Some(fan_curve) = args.fan_curve {
+    Ok(())
+}
+
+/// loads `config_path`, applies every configured gpu's static settings,
+/// then runs the fan-control loop for every configured gpu concurrently
+/// (one thread per device). shuts down on ctrl-c and reloads
+/// `config_path` on sighup, re-applying any changed static settings
+/// (tdp, clock offsets/locks) alongside the fan curve.
+///
+/// the managed gpu *set* is fixed for the life of the daemon: one
+/// thread is spawned per `[[gpu]]` entry present at startup, each
+/// resolving its device handle once. a sighup reload only hot-swaps the
+/// fan curve and static settings of entries that still exist at their
+/// original index — removing a `[[gpu]]` entry parks its thread instead
+/// of indexing out of bounds, and adding one or changing an existing
+/// entry's `uuid`/`pci_bus_id`/`index` has no effect until restart.
+fn run_daemon(lib: &NvmlLib, config_path: &Path) -> Result<()> {
+    let config = Arc::new(Mutex::new(Config::load(config_path)?));
+    if config.lock().unwrap().gpus.is_empty() {
+        bail!(
+            "config file {} has no [[gpu]] entries!",
+            config_path.display()
+        );
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    {
         let (tx, rx) = mpsc::channel();
         ctrlc::set_handler(move || tx.send(()).unwrap())?;
+        let stop = Arc::clone(&stop);
+        thread::spawn(move || {
+            let _ = rx.recv();
+            info!("received ctrl-c, shutting down daemon!");
+            stop.store(true, Ordering::SeqCst);
+        });
+    }
 
-        if fan_curve.len() == 1 && fan_curve[0].0 > 0 {
-            error!("single point fan curve must have a 0c point!");
-        } else {
-            loop {
-                if let Ok(()) = rx.try_recv() {
-                    break;
+    {
+        let config = Arc::clone(&config);
+        let config_path = config_path.to_path_buf();
+        let mut signals = Signals::new([SIGHUP])?;
+        thread::spawn(move || {
+            for _ in signals.forever() {
+                info!(
+                    "received sighup, reloading config from {}!",
+                    config_path.display()
+                );
+                match Config::load(&config_path) {
+                    Ok(new_config) => *config.lock().unwrap() = new_config,
+                    Err(err) => error!("failed to reload config, keeping old one! ({err})"),
                 }
+            }
+        });
+    }
 
-                let mut temp = 0;
-                match unsafe { lib.nvmlDeviceGetTemperature(device, 0, &mut temp) } {
-                    0 => info!("read current temperature! ({temp}c)"),
-                    val => error!("failed to read current temperature! (error = {val})"),
+    let gpu_count = config.lock().unwrap().gpus.len();
+    thread::scope(|scope| {
+        for gpu_idx in 0..gpu_count {
+            let config = Arc::clone(&config);
+            let stop = Arc::clone(&stop);
+            scope.spawn(move || {
+                if let Err(err) = run_daemon_gpu(lib, &config, gpu_idx, &stop) {
+                    error!("gpu thread #{gpu_idx} exited! ({err})");
                 }
+            });
+        }
+    });
+
+    unsafe {
+        lib.nvmlShutdown();
+    }
 
-                // find neighboring keypoints
-                let ((temp_pre, duty_pre), (temp_post, duty_post)) = match &fan_curve[..] {
-                    [point] => (*point, (100, 100)),
-                    points => points
-                        .windows(2)
-                        .find(|window| window[0].0 < temp && window[1].0 > temp)
-                        .map(|window| (window[0], window[1]))
-                        .unwrap_or(((0, 0), (100, 100))),
-                };
-
-                let slope = (duty_post + duty_pre) as f64 / (temp_post + temp_pre) as f64;
-                let duty = (temp as f64 * slope) as u32;
-                match unsafe { lib.nvmlDeviceSetFanSpeed_v2(device, 0, duty) } {
-                    0 => info!("set fan duty to {duty}%!"),
-                    val => error!("failed to set fan duty! (error = {val})"),
+    Ok(())
+}
+
+/// resolves the device for a single `[[gpu]]` entry, applies its static
+/// settings, then runs its fan-control loop until `stop` is set,
+/// re-reading the (possibly sighup-reloaded) fan curve and static
+/// settings every tick and re-applying the latter whenever they change.
+/// if a reload removes this thread's `gpu_idx` from the config, parks
+/// (polling for it to reappear) instead of indexing out of bounds.
+fn run_daemon_gpu(
+    lib: &NvmlLib,
+    config: &Mutex<Config>,
+    gpu_idx: usize,
+    stop: &AtomicBool,
+) -> Result<()> {
+    let device = resolve_device(lib, &config.lock().unwrap().gpus[gpu_idx])?;
+    let is_mig_child = device::is_mig_device_handle(lib, device)?;
+
+    match device::mig_instances(lib, device) {
+        Ok(instances) if !instances.is_empty() => info!(
+            "gpu #{gpu_idx}: mig mode enabled with {} instance(s)!",
+            instances.len()
+        ),
+        Ok(_) => {}
+        Err(err) => error!("gpu #{gpu_idx}: failed to enumerate mig instances! ({err})"),
+    }
+
+    let mut static_settings = static_settings_of(&config.lock().unwrap().gpus[gpu_idx]);
+    apply_static_settings(lib, device, is_mig_child, &static_settings);
+
+    let mut hysteresis = fan::Hysteresis::new();
+    let mut removed_by_reload = false;
+    loop {
+        if stop.load(Ordering::SeqCst) {
+            break;
+        }
+
+        let Some((fan_curve, update_duration, fan_hysteresis, fan_min_delta, new_static_settings)) =
+            ({
+                let config = config.lock().unwrap();
+                // a reload can shrink `gpus` out from under this thread's
+                // index (the gpu set itself isn't hot-reloadable, only
+                // the fan curve and static settings are — see
+                // `run_daemon`'s doc comment); park instead of indexing
+                // out of bounds.
+                config.gpus.get(gpu_idx).map(|gpu| {
+                    (
+                        gpu.fan_curve.clone(),
+                        config.fan_update_duration,
+                        config.fan_hysteresis,
+                        config.fan_min_delta,
+                        static_settings_of(gpu),
+                    )
+                })
+            })
+        else {
+            if !removed_by_reload {
+                error!("gpu #{gpu_idx}: config entry removed by a reload, parking this thread!");
+                removed_by_reload = true;
+            }
+            thread::sleep(Duration::from_secs(1));
+            continue;
+        };
+        removed_by_reload = false;
+
+        if new_static_settings != static_settings {
+            info!("gpu #{gpu_idx}: static settings changed after reload, re-applying!");
+            apply_static_settings(lib, device, is_mig_child, &new_static_settings);
+            static_settings = new_static_settings;
+        }
+
+        if !fan_curve.is_empty() && is_mig_child {
+            error!(
+                "gpu #{gpu_idx}: {}",
+                NvmlError::mig_child_unsupported("fan control")
+            );
+        } else if !fan_curve.is_empty() {
+            let mut temp = 0;
+            match check(lib, unsafe {
+                lib.nvmlDeviceGetTemperature(device, 0, &mut temp)
+            }) {
+                Ok(()) => info!("gpu #{gpu_idx}: read current temperature! ({temp}c)"),
+                Err(err) => error!("gpu #{gpu_idx}: failed to read current temperature! ({err})"),
+            }
+
+            if let Some(duty) = hysteresis.tick(&fan_curve, temp, fan_hysteresis, fan_min_delta) {
+                match check(lib, unsafe {
+                    lib.nvmlDeviceSetFanSpeed_v2(device, 0, duty)
+                }) {
+                    Ok(()) => info!("gpu #{gpu_idx}: set fan duty to {duty}%!"),
+                    Err(err) => error!("gpu #{gpu_idx}: failed to set fan duty! ({err})"),
                 }
+            }
+        }
+
+        thread::sleep(Duration::from_secs(update_duration));
+    }
+
+    Ok(())
+}
+
+/// resolves a `[[gpu]]` entry's device handle, preferring its uuid, then
+/// its pci bus id, then its index.
+fn resolve_device(lib: &NvmlLib, gpu: &GpuConfig) -> Result<nvmlDevice_t> {
+    if let Some(uuid) = &gpu.uuid {
+        return device::by_uuid(lib, uuid);
+    }
+
+    if let Some(pci_bus_id) = &gpu.pci_bus_id {
+        return device::by_pci_bus_id(lib, pci_bus_id);
+    }
+
+    let index = gpu
+        .index
+        .ok_or_else(|| eyre!("[[gpu]] entry must have an index, uuid, or pci bus id!"))?;
+    device::by_index(lib, index)
+}
+
+/// the static (one-shot, non-fan) settings to apply to a device: tdp,
+/// the relative vf clock offsets, and the locked clock ranges.
+#[derive(Clone, PartialEq, Eq)]
+struct StaticSettings {
+    tdp: Option<u32>,
+    mclk_offset: Option<i32>,
+    gclk_offset: Option<i32>,
+    gclk_lock: Option<(u32, u32)>,
+    mclk_lock: Option<(u32, u32)>,
+    reset_clocks: bool,
+}
 
-                thread::sleep(Duration::from_secs(args.fan_update_duration));
+/// reads `gpu`'s static settings out of its `[[gpu]]` entry, so they can
+/// be compared against the last-applied set and re-applied on change.
+fn static_settings_of(gpu: &GpuConfig) -> StaticSettings {
+    StaticSettings {
+        tdp: gpu.tdp,
+        mclk_offset: gpu.mclk_offset,
+        gclk_offset: gpu.gclk_offset,
+        gclk_lock: gpu.gclk_lock,
+        mclk_lock: gpu.mclk_lock,
+        reset_clocks: gpu.reset_clocks,
+    }
+}
+
+/// applies `settings` to `device`. `is_mig_child` gates every setting
+/// below: they're all physically global to the gpu, so a mig instance
+/// handle can't accept them and is rejected with a clear error instead
+/// of forwarding the call into nvml.
+fn apply_static_settings(
+    lib: &NvmlLib,
+    device: nvmlDevice_t,
+    is_mig_child: bool,
+    settings: &StaticSettings,
+) {
+    if let Some(tdp) = settings.tdp {
+        let result = if is_mig_child {
+            Err(NvmlError::mig_child_unsupported("tdp"))
+        } else {
+            check(lib, unsafe {
+                lib.nvmlDeviceSetPowerManagementLimit(device, tdp * 1000)
+            })
+        };
+        match result {
+            Ok(()) => info!("set tdp to {tdp}W!"),
+            Err(err) => error!("failed to set tdp! ({err})"),
+        }
+    }
+
+    if let Some(mem_clock) = settings.mclk_offset {
+        let result = if is_mig_child {
+            Err(NvmlError::mig_child_unsupported("memory clock offset"))
+        } else {
+            check(lib, unsafe {
+                lib.nvmlDeviceSetMemClkVfOffset(device, mem_clock * 2)
+            })
+        };
+        match result {
+            Ok(()) => info!("set memory clock offset to +{mem_clock}MHz!"),
+            Err(err) if err.is_not_supported() => {
+                info!("memory clock offset not supported on this gpu, skipping! ({err})")
             }
+            Err(err) => error!("failed to set memory clock offset! ({err})"),
         }
     }
 
-    unsafe {
-        lib.nvmlShutdown();
+    if let Some(gfx_clock) = settings.gclk_offset {
+        let result = if is_mig_child {
+            Err(NvmlError::mig_child_unsupported("graphics clock offset"))
+        } else {
+            check(lib, unsafe {
+                lib.nvmlDeviceSetGpcClkVfOffset(device, gfx_clock)
+            })
+        };
+        match result {
+            Ok(()) => info!("set graphics clock offset to +{gfx_clock}MHz!"),
+            Err(err) if err.is_not_supported() => {
+                info!("graphics clock offset not supported on this gpu, skipping! ({err})")
+            }
+            Err(err) => error!("failed to set graphics clock! ({err})"),
+        }
     }
 
-    Ok(())
+    if settings.reset_clocks {
+        let reset_gpu = if is_mig_child {
+            Err(NvmlError::mig_child_unsupported(
+                "resetting gpu locked clocks",
+            ))
+        } else {
+            check(lib, unsafe { lib.nvmlDeviceResetGpuLockedClocks(device) })
+        };
+        match reset_gpu {
+            Ok(()) => info!("reset gpu locked clocks!"),
+            Err(err) if err.is_not_supported() => {
+                info!("resetting gpu locked clocks not supported on this gpu, skipping! ({err})")
+            }
+            Err(err) => error!("failed to reset gpu locked clocks! ({err})"),
+        }
+
+        let reset_memory = if is_mig_child {
+            Err(NvmlError::mig_child_unsupported(
+                "resetting memory locked clocks",
+            ))
+        } else {
+            check(lib, unsafe {
+                lib.nvmlDeviceResetMemoryLockedClocks(device)
+            })
+        };
+        match reset_memory {
+            Ok(()) => info!("reset memory locked clocks!"),
+            Err(err) if err.is_not_supported() => {
+                info!("resetting memory locked clocks not supported on this gpu, skipping! ({err})")
+            }
+            Err(err) => error!("failed to reset memory locked clocks! ({err})"),
+        }
+    }
+
+    if let Some((min, max)) = settings.gclk_lock {
+        let result = if is_mig_child {
+            Err(NvmlError::mig_child_unsupported("gpu clock locking"))
+        } else {
+            check(lib, unsafe {
+                lib.nvmlDeviceSetGpuLockedClocks(device, min, max)
+            })
+        };
+        match result {
+            Ok(()) => info!("locked gpu clock to {min}-{max}MHz!"),
+            Err(err) if err.is_not_supported() => {
+                info!("gpu clock locking not supported on this gpu, skipping! ({err})")
+            }
+            Err(err) => error!("failed to lock gpu clock! ({err})"),
+        }
+    }
+
+    if let Some((min, max)) = settings.mclk_lock {
+        let result = if is_mig_child {
+            Err(NvmlError::mig_child_unsupported("memory clock locking"))
+        } else {
+            check(lib, unsafe {
+                lib.nvmlDeviceSetMemoryLockedClocks(device, min, max)
+            })
+        };
+        match result {
+            Ok(()) => info!("locked memory clock to {min}-{max}MHz!"),
+            Err(err) if err.is_not_supported() => {
+                info!("memory clock locking not supported on this gpu, skipping! ({err})")
+            }
+            Err(err) => error!("failed to lock memory clock! ({err})"),
+        }
+    }
 }
 
 #[derive(Debug, Parser)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// the index of the gpu
-    #[arg(short, long, default_value_t = 0)]
-    index: u32,
+    /// selects the gpu(s) to manage: an index, a uuid (`GPU-xxxx`, or
+    /// `MIG-xxxx` for a mig instance), a pci bus id (e.g.
+    /// `0000:01:00.0`), or `all`
+    #[arg(short, long, default_value = "0")]
+    device: device::Selector,
 
     /// tdp
     #[arg(short, long, value_name = "W")]
@@ -150,9 +567,72 @@ struct Args {
     #[arg(short = 'r', long, value_name = "(SECS)", default_value_t = 2)]
     fan_update_duration: u64,
 
+    /// dead-band (in celsius) around the point that last triggered a
+    /// fan duty change, to stop the fan oscillating near a curve
+    /// keypoint
+    #[arg(long, value_name = "CEL", default_value_t = 2)]
+    fan_hysteresis: u32,
+
+    /// suppress fan duty changes smaller than this many percent
+    #[arg(long, value_name = "PERCENT", default_value_t = 0)]
+    fan_min_delta: u32,
+
     /// logfile location
     #[arg(short, long, default_value = "nvml-tune.log")]
     logfile: PathBuf,
+
+    /// path to a toml config file describing one or more gpus to manage,
+    /// for use with --daemon
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// run as a persistent, systemd-installable daemon: load --config,
+    /// apply every gpu's static settings, then run the fan-control
+    /// loop for every configured gpu concurrently. reloads --config on
+    /// sighup, re-applying any static settings the reload changed
+    #[arg(long, requires = "config")]
+    daemon: bool,
+
+    /// write gpu telemetry as influxdb line-protocol records to this
+    /// file (or `-` for stdout), polling once per --metrics-interval
+    #[arg(long, value_name = "PATH")]
+    metrics: Option<PathBuf>,
+
+    /// how long to sleep in between metrics samples
+    #[arg(long, value_name = "SECS", default_value_t = 1)]
+    metrics_interval: u64,
+
+    /// lock the graphics clock to a MIN:MAX range (MHz), for deterministic
+    /// clock pinning instead of the relative --gclk-offset
+    #[arg(long, value_name = "MIN:MAX", value_parser = parse_clock_range)]
+    gclk_lock: Option<(u32, u32)>,
+
+    /// lock the memory clock to a MIN:MAX range (MHz), for deterministic
+    /// clock pinning instead of the relative --mclk-offset
+    #[arg(long, value_name = "MIN:MAX", value_parser = parse_clock_range)]
+    mclk_lock: Option<(u32, u32)>,
+
+    /// reset any locked graphics/memory clocks back to the gpu's defaults
+    #[arg(long)]
+    reset_clocks: bool,
+}
+
+fn parse_clock_range(s: &str) -> Result<(u32, u32), clap::Error> {
+    let invalid = || {
+        clap::Error::raw(
+            clap::error::ErrorKind::InvalidValue,
+            "expected a MIN:MAX clock range, e.g. 210:2100\n",
+        )
+    };
+
+    let (min, max) = s.split_once(':').ok_or_else(invalid)?;
+    let min = min.parse::<u32>().map_err(|_| invalid())?;
+    let max = max.parse::<u32>().map_err(|_| invalid())?;
+    if min > max {
+        return Err(invalid());
+    }
+
+    Ok((min, max))
 }
 
 fn parse_fan_curve(i: &str) -> Result<Vec<(u32, u32)>, clap::Error> {
@@ -167,54 +647,40 @@ fn parse_fan_curve(i: &str) -> Result<Vec<(u32, u32)>, clap::Error> {
         )(i)?;
         let (i, _) = tag(")")(i)?;
 
-        let temp = temp.parse::<u32>().unwrap();
-        if temp > 100 {
-            return Err(nom::Err::Error(nom::error::Error::new(
-                i,
-                nom::error::ErrorKind::Digit,
-            )));
-        }
-        let duty = duty.parse::<u32>().unwrap();
-        if duty > 100 {
-            return Err(nom::Err::Error(nom::error::Error::new(
-                i,
-                nom::error::ErrorKind::Digit,
-            )));
-        }
-
-        Ok((i, (temp, duty)))
+        Ok((i, (temp.parse().unwrap(), duty.parse().unwrap())))
     }
 
-    let mut curve = Vec::new();
+    let mut points = Vec::new();
     let mut i = i;
 
     while let Ok((i_next, point)) = alt((terminated(parse_pair, tag(",")), parse_pair))(i) {
         i = i_next;
-
-        if let Some(idx) = curve.iter().position(|(temp, _)| *temp == point.0) {
-            if point.1 < curve[idx].1 {
-                continue;
-            }
-            curve.remove(idx);
-        }
-        curve.push(point);
+        points.push(point);
     }
 
-    if curve.is_empty() {
-        return Err(clap::Error::raw(
-            clap::error::ErrorKind::InvalidValue,
-            "fan curve must not be empty!",
-        ));
-    }
-    curve.sort_by_key(|(temp, _)| *temp);
+    // shares the sort/dedup/anchor pass with `GpuConfig::fan_curve` (see
+    // `Config::load`), so a CLI curve and the identical TOML curve
+    // evaluate identically.
+    fan::normalize_curve(&points)
+        .map_err(|err| clap::Error::raw(clap::error::ErrorKind::InvalidValue, format!("{err}\n")))
+}
 
-    if curve[0].0 != 0 {
-        curve.insert(0, (0, 0));
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    if curve.last().is_some_and(|(temp, _)| *temp < 100) {
-        curve.push((100, 100));
+    #[test]
+    fn parse_clock_range_accepts_min_max() {
+        assert_eq!(parse_clock_range("210:2100").unwrap(), (210, 2100));
+        assert_eq!(parse_clock_range("0:0").unwrap(), (0, 0));
     }
 
-    Ok(curve)
+    #[test]
+    fn parse_clock_range_rejects_malformed_or_inverted_input() {
+        assert!(parse_clock_range("2100").is_err());
+        assert!(parse_clock_range("210:").is_err());
+        assert!(parse_clock_range(":2100").is_err());
+        assert!(parse_clock_range("abc:2100").is_err());
+        assert!(parse_clock_range("2100:210").is_err()); // min > max
+    }
 }
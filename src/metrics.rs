@@ -0,0 +1,125 @@
+//! `--metrics`: polls nvml telemetry and emits it as InfluxDB
+//! line-protocol records to a file or stdout.
+
+use crate::error::check;
+use eyre::Result;
+use nvml_wrapper_sys::bindings::{nvmlDevice_t, nvmlMemory_t, nvmlUtilization_t, NvmlLib};
+use std::{
+    ffi::CStr,
+    fs::File,
+    io::{self, Write},
+    mem::MaybeUninit,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tracing::error;
+
+/// polls `device` every `interval` seconds and appends one line-protocol
+/// record per sample to `path` (`-` meaning stdout), until `stop` is set.
+pub fn run(
+    lib: &NvmlLib,
+    device: nvmlDevice_t,
+    index: u32,
+    path: &Path,
+    interval: u64,
+    stop: &AtomicBool,
+) -> Result<()> {
+    let uuid = device_uuid(lib, device)?;
+
+    let mut out: Box<dyn Write> = if path == Path::new("-") {
+        Box::new(io::stdout())
+    } else {
+        Box::new(File::options().create(true).append(true).open(path)?)
+    };
+
+    loop {
+        if stop.load(Ordering::SeqCst) {
+            break;
+        }
+
+        if let Err(err) = write_sample(lib, device, index, &uuid, &mut out) {
+            error!("failed to write metrics sample! ({err})");
+        }
+
+        thread::sleep(Duration::from_secs(interval));
+    }
+
+    Ok(())
+}
+
+/// reads one telemetry sample from `device` and writes it as a
+/// `nvml_tune` line-protocol record to `out`.
+fn write_sample(
+    lib: &NvmlLib,
+    device: nvmlDevice_t,
+    index: u32,
+    uuid: &str,
+    out: &mut impl Write,
+) -> Result<()> {
+    let mut temp = 0;
+    check(lib, unsafe {
+        lib.nvmlDeviceGetTemperature(device, 0, &mut temp)
+    })?;
+
+    let mut power_mw = 0;
+    check(lib, unsafe {
+        lib.nvmlDeviceGetPowerUsage(device, &mut power_mw)
+    })?;
+
+    let mut util = unsafe { MaybeUninit::<nvmlUtilization_t>::zeroed().assume_init() };
+    check(lib, unsafe {
+        lib.nvmlDeviceGetUtilizationRates(device, &mut util)
+    })?;
+
+    let mut gfx_clk = 0;
+    check(lib, unsafe {
+        lib.nvmlDeviceGetClockInfo(device, 0 /* graphics */, &mut gfx_clk)
+    })?;
+
+    let mut sm_clk = 0;
+    check(lib, unsafe {
+        lib.nvmlDeviceGetClockInfo(device, 1 /* sm */, &mut sm_clk)
+    })?;
+
+    let mut mem_clk = 0;
+    check(lib, unsafe {
+        lib.nvmlDeviceGetClockInfo(device, 2 /* memory */, &mut mem_clk)
+    })?;
+
+    let mut mem = unsafe { MaybeUninit::<nvmlMemory_t>::zeroed().assume_init() };
+    check(lib, unsafe {
+        lib.nvmlDeviceGetMemoryInfo(device, &mut mem)
+    })?;
+
+    let mut fan = 0;
+    check(lib, unsafe {
+        lib.nvmlDeviceGetFanSpeed_v2(device, 0, &mut fan)
+    })?;
+
+    let unix_nanos = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos();
+
+    writeln!(
+        out,
+        "nvml_tune,gpu={index},uuid={uuid} temp={temp}i,power_w={power_w},gpu_util={gpu_util}i,mem_util={mem_util}i,gfx_clk={gfx_clk}i,sm_clk={sm_clk}i,mem_clk={mem_clk}i,mem_used={mem_used}i,fan={fan}i {unix_nanos}",
+        power_w = power_mw as f64 / 1000.0,
+        gpu_util = util.gpu,
+        mem_util = util.memory,
+        mem_used = mem.used,
+    )?;
+    out.flush()?;
+
+    Ok(())
+}
+
+/// reads `device`'s uuid (`GPU-xxxx`) via nvml.
+fn device_uuid(lib: &NvmlLib, device: nvmlDevice_t) -> Result<String> {
+    let mut buf = [0i8; 96];
+    check(lib, unsafe {
+        lib.nvmlDeviceGetUUID(device, buf.as_mut_ptr(), buf.len() as u32)
+    })?;
+    Ok(unsafe { CStr::from_ptr(buf.as_ptr()) }
+        .to_string_lossy()
+        .into_owned())
+}